@@ -0,0 +1,77 @@
+//! Child-process transport: spawn a command and speak JSON-RPC over its stdio.
+
+#[cfg(feature = "child-process")]
+use crate::{Transport, ServerError};
+#[cfg(feature = "child-process")]
+use async_trait::async_trait;
+#[cfg(feature = "child-process")]
+use serde_json::Value;
+#[cfg(feature = "child-process")]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(feature = "child-process")]
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+#[cfg(feature = "child-process")]
+/// Transport that spawns a command and frames JSON-RPC, newline-delimited
+/// the same way as [`crate::StdioTransport`], over its stdin/stdout. Lets a
+/// server built with this crate proxy requests to a downstream MCP/JSON-RPC
+/// process, mirroring how DAP clients speak to a spawned backend.
+pub struct ChildProcessTransport {
+    child: Child,
+    reader: BufReader<ChildStdout>,
+    writer: ChildStdin,
+}
+
+#[cfg(feature = "child-process")]
+impl ChildProcessTransport {
+    /// Spawn `command`, taking ownership of its stdin/stdout for framing.
+    pub fn spawn(mut command: Command) -> Result<Self, ServerError> {
+        command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = command.spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| ServerError::Transport("Child process has no stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| ServerError::Transport("Child process has no stdout".to_string()))?;
+
+        Ok(Self {
+            child,
+            reader: BufReader::new(stdout),
+            writer: stdin,
+        })
+    }
+
+    /// The spawned child's OS process id, for diagnostics.
+    pub fn id(&self) -> Option<u32> {
+        self.child.id()
+    }
+}
+
+#[cfg(feature = "child-process")]
+#[async_trait]
+impl Transport for ChildProcessTransport {
+    async fn receive_message(&mut self) -> Result<Value, ServerError> {
+        let mut line = String::new();
+        let bytes_read = self.reader.read_line(&mut line).await?;
+        if bytes_read == 0 {
+            return Err(ServerError::Transport("Child process closed stdout".to_string()));
+        }
+        let message: Value = serde_json::from_str(&line)?;
+        Ok(message)
+    }
+
+    async fn send_message(&mut self, message: Value) -> Result<(), ServerError> {
+        let json = serde_json::to_string(&message)?;
+        self.writer.write_all(json.as_bytes()).await?;
+        self.writer.write_all(b"\n").await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use mcp_protocol_types::*;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 
 /// Tool handler function type
 pub type ToolHandler = Box<dyn Fn(CallToolRequest) -> Pin<Box<dyn Future<Output = Result<CallToolResult, McpError>> + Send>> + Send + Sync>;
@@ -12,4 +13,22 @@ pub type ToolHandler = Box<dyn Fn(CallToolRequest) -> Pin<Box<dyn Future<Output
 pub type ResourceHandler = Box<dyn Fn(ReadResourceRequest) -> Pin<Box<dyn Future<Output = Result<ReadResourceResult, McpError>> + Send>> + Send + Sync>;
 
 /// Prompt handler function type
-pub type PromptHandler = Box<dyn Fn(GetPromptRequest) -> Pin<Box<dyn Future<Output = Result<GetPromptResult, McpError>> + Send>> + Send + Sync>;
\ No newline at end of file
+pub type PromptHandler = Box<dyn Fn(GetPromptRequest) -> Pin<Box<dyn Future<Output = Result<GetPromptResult, McpError>> + Send>> + Send + Sync>;
+
+/// Notification handler function type.
+///
+/// Unlike the other handler types, a notification has no `id` and therefore
+/// no response to produce; the handler runs for its side effects only.
+pub type NotificationHandler = Box<dyn Fn(JsonRpcRequest) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Extracts and deserializes a call's arguments into a typed value `T`,
+/// for use with [`Server::set_tool_handler_typed`](crate::Server::set_tool_handler_typed).
+///
+/// Deserialization failures are turned into an `invalid_params` JSON-RPC
+/// error automatically, so handlers never see malformed input.
+pub struct Params<T>(pub T);
+
+/// Extracts the shared application state registered via
+/// [`ServerBuilder::with_state`](crate::ServerBuilder::with_state), for use
+/// with [`Server::set_tool_handler_typed`](crate::Server::set_tool_handler_typed).
+pub struct State<S>(pub Arc<S>);
\ No newline at end of file
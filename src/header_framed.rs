@@ -0,0 +1,134 @@
+//! Content-Length header-framed transport, as used by LSP and many MCP hosts.
+
+#[cfg(feature = "header-framed")]
+use crate::{Transport, ServerError};
+#[cfg(feature = "header-framed")]
+use async_trait::async_trait;
+#[cfg(feature = "header-framed")]
+use serde_json::Value;
+#[cfg(feature = "header-framed")]
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, stdin, stdout};
+
+#[cfg(feature = "header-framed")]
+/// A transport that frames messages the way LSP (and many MCP hosts) do:
+/// a `Content-Length: <n>` header, a blank line, then exactly `<n>` bytes
+/// of UTF-8 JSON. Unlike newline-delimited framing, this tolerates message
+/// bodies that contain embedded newlines.
+pub struct HeaderFramedTransport<R, W> {
+    reader: BufReader<R>,
+    writer: W,
+}
+
+#[cfg(feature = "header-framed")]
+impl<R, W> HeaderFramedTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    /// Create a new header-framed transport over the given reader and writer.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+        }
+    }
+}
+
+#[cfg(feature = "header-framed")]
+impl HeaderFramedTransport<tokio::io::Stdin, tokio::io::Stdout> {
+    /// Create a header-framed transport wrapping `stdin()`/`stdout()`, for
+    /// servers that want LSP-style framing instead of newline-delimited JSON.
+    pub fn stdio() -> Self {
+        Self::new(stdin(), stdout())
+    }
+}
+
+#[cfg(feature = "header-framed")]
+#[async_trait]
+impl<R, W> Transport for HeaderFramedTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    async fn receive_message(&mut self) -> Result<Value, ServerError> {
+        let mut content_length: Option<usize> = None;
+        loop {
+            let mut line = String::new();
+            let bytes_read = self.reader.read_line(&mut line).await?;
+            if bytes_read == 0 {
+                return Err(ServerError::Transport("Transport closed while reading headers".to_string()));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = Some(value.trim().parse().map_err(|_| {
+                        ServerError::Protocol(format!("Invalid Content-Length header: {value}"))
+                    })?);
+                }
+                // Other headers (e.g. Content-Type) are accepted and ignored.
+            }
+        }
+
+        let content_length = content_length
+            .ok_or_else(|| ServerError::Protocol("Missing Content-Length header".to_string()))?;
+
+        let mut body = vec![0u8; content_length];
+        self.reader.read_exact(&mut body).await?;
+        let message: Value = serde_json::from_slice(&body)?;
+        Ok(message)
+    }
+
+    async fn send_message(&mut self, message: Value) -> Result<(), ServerError> {
+        let body = serde_json::to_vec(&message)?;
+        self.writer
+            .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+            .await?;
+        self.writer.write_all(&body).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "header-framed"))]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    async fn receive(bytes: &[u8]) -> Result<Value, ServerError> {
+        let mut transport = HeaderFramedTransport::new(Cursor::new(bytes.to_vec()), Vec::new());
+        transport.receive_message().await
+    }
+
+    #[tokio::test]
+    async fn parses_a_well_formed_message() {
+        let message = receive(b"Content-Length: 15\r\n\r\n{\"jsonrpc\":\"2\"}").await.unwrap();
+        assert_eq!(message, serde_json::json!({ "jsonrpc": "2" }));
+    }
+
+    #[tokio::test]
+    async fn header_name_match_is_case_insensitive() {
+        let message = receive(b"content-LENGTH: 15\r\n\r\n{\"jsonrpc\":\"2\"}").await.unwrap();
+        assert_eq!(message, serde_json::json!({ "jsonrpc": "2" }));
+    }
+
+    #[tokio::test]
+    async fn last_content_length_header_wins() {
+        let message = receive(b"Content-Length: 1\r\nContent-Length: 15\r\n\r\n{\"jsonrpc\":\"2\"}").await.unwrap();
+        assert_eq!(message, serde_json::json!({ "jsonrpc": "2" }));
+    }
+
+    #[tokio::test]
+    async fn missing_content_length_header_is_a_protocol_error() {
+        let err = receive(b"Content-Type: application/json\r\n\r\n{}").await.unwrap_err();
+        assert!(matches!(err, ServerError::Protocol(msg) if msg.contains("Missing Content-Length")));
+    }
+
+    #[tokio::test]
+    async fn malformed_content_length_header_is_a_protocol_error() {
+        let err = receive(b"Content-Length: not-a-number\r\n\r\n{}").await.unwrap_err();
+        assert!(matches!(err, ServerError::Protocol(msg) if msg.contains("Invalid Content-Length")));
+    }
+}
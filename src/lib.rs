@@ -63,6 +63,15 @@ pub mod handlers;
 #[cfg(feature = "stdio")]
 pub mod stdio;
 
+#[cfg(feature = "header-framed")]
+pub mod header_framed;
+
+#[cfg(feature = "tcp")]
+pub mod tcp;
+
+#[cfg(feature = "child-process")]
+pub mod child_process;
+
 pub use server::{Server, ServerBuilder};
 pub use error::ServerError;
 pub use handlers::*;
@@ -71,5 +80,14 @@ pub use transport::Transport;
 #[cfg(feature = "stdio")]
 pub use stdio::StdioTransport;
 
+#[cfg(feature = "header-framed")]
+pub use header_framed::HeaderFramedTransport;
+
+#[cfg(feature = "tcp")]
+pub use tcp::TcpTransport;
+
+#[cfg(feature = "child-process")]
+pub use child_process::ChildProcessTransport;
+
 // Re-export commonly used types
 pub use mcp_protocol_types::*;
\ No newline at end of file
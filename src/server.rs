@@ -1,21 +1,62 @@
 //! Server implementation.
 
-use crate::{ServerError, ToolHandler, ResourceHandler, PromptHandler, Transport};
+use crate::{ServerError, ToolHandler, ResourceHandler, PromptHandler, NotificationHandler, Params, State, Transport};
 use mcp_protocol_types::*;
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio::task::AbortHandle;
 
-/// MCP Server implementation
+/// Capacity of the internal broadcast channel used to fan out
+/// `notifications/resources/updated` pushes to the run loop.
+const RESOURCE_UPDATE_CHANNEL_CAPACITY: usize = 64;
+
+/// Protocol versions this server understands. `initialize` accepts a client
+/// only if it requests one of these exactly; there is currently a single
+/// supported version, so this validates an exact match rather than
+/// negotiating down to a highest mutually supported version.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[MCP_VERSION];
+
+/// MCP Server implementation.
+///
+/// Cheaply `Clone`-able: it is a thin handle around an `Arc`-shared inner
+/// state, which is what lets [`Server::run`] hand a copy to each spawned
+/// request task.
+#[derive(Clone)]
 pub struct Server {
+    inner: Arc<ServerInner>,
+}
+
+/// State scoped to a single [`Server::run`] invocation, i.e. one connection.
+///
+/// Resource subscriptions, the negotiated protocol version, and in-flight
+/// request tracking must not leak between independently connected clients
+/// sharing one `Server` (e.g. via [`Server::serve_tcp`]) — two connections
+/// can easily pick the same JSON-RPC request id, so keying any of this by id
+/// alone in shared server state would let one connection cancel or observe
+/// another's requests. All of it therefore lives here rather than on
+/// `ServerInner`.
+#[derive(Default)]
+struct ConnectionState {
+    subscribed_resources: RwLock<HashSet<String>>,
+    negotiated_version: RwLock<Option<String>>,
+    in_flight: Mutex<HashMap<String, AbortHandle>>,
+}
+
+struct ServerInner {
     info: Implementation,
     capabilities: ServerCapabilities,
+    instructions: Option<String>,
     tools: Vec<Tool>,
     resources: Vec<Resource>,
     prompts: Vec<Prompt>,
     tool_handlers: Arc<RwLock<HashMap<String, ToolHandler>>>,
     resource_handlers: Arc<RwLock<HashMap<String, ResourceHandler>>>,
     prompt_handlers: Arc<RwLock<HashMap<String, PromptHandler>>>,
+    notification_handlers: Arc<RwLock<HashMap<String, NotificationHandler>>>,
+    resource_updates: broadcast::Sender<String>,
+    state: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 /// Builder for creating MCP servers
@@ -27,6 +68,7 @@ pub struct ServerBuilder {
     tools: Vec<Tool>,
     resources: Vec<Resource>,
     prompts: Vec<Prompt>,
+    state: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 impl ServerBuilder {
@@ -40,6 +82,7 @@ impl ServerBuilder {
             tools: Vec::new(),
             resources: Vec::new(),
             prompts: Vec::new(),
+            state: None,
         }
     }
 
@@ -73,28 +116,45 @@ impl ServerBuilder {
         self
     }
 
+    /// Register shared application state (config, a database handle, a
+    /// client, ...) for handlers registered with
+    /// [`Server::set_tool_handler_typed`] to extract via [`State`].
+    pub fn with_state<S: Send + Sync + 'static>(mut self, state: S) -> Self {
+        self.state = Some(Arc::new(state));
+        self
+    }
+
     /// Build the server
     pub fn build(self) -> Server {
         let capabilities = ServerCapabilities {
             tools: if self.tools.is_empty() { None } else { Some(ToolsCapability { list_changed: None }) },
-            resources: if self.resources.is_empty() { None } else { Some(ResourcesCapability { subscribe: None, list_changed: None }) },
+            // `list_changed: None` because the resource list is fixed at `build()` time;
+            // nothing in this crate adds or removes resources afterwards or emits
+            // `notifications/resources/list_changed`.
+            resources: if self.resources.is_empty() { None } else { Some(ResourcesCapability { subscribe: Some(true), list_changed: None }) },
             prompts: if self.prompts.is_empty() { None } else { Some(PromptsCapability { list_changed: None }) },
             logging: Some(LoggingCapability {}),
             experimental: None,
         };
 
         Server {
-            info: Implementation {
-                name: self.name,
-                version: self.version,
-            },
-            capabilities,
-            tools: self.tools,
-            resources: self.resources,
-            prompts: self.prompts,
-            tool_handlers: Arc::new(RwLock::new(HashMap::new())),
-            resource_handlers: Arc::new(RwLock::new(HashMap::new())),
-            prompt_handlers: Arc::new(RwLock::new(HashMap::new())),
+            inner: Arc::new(ServerInner {
+                info: Implementation {
+                    name: self.name,
+                    version: self.version,
+                },
+                capabilities,
+                instructions: self.instructions,
+                tools: self.tools,
+                resources: self.resources,
+                prompts: self.prompts,
+                tool_handlers: Arc::new(RwLock::new(HashMap::new())),
+                resource_handlers: Arc::new(RwLock::new(HashMap::new())),
+                prompt_handlers: Arc::new(RwLock::new(HashMap::new())),
+                notification_handlers: Arc::new(RwLock::new(HashMap::new())),
+                resource_updates: broadcast::channel(RESOURCE_UPDATE_CHANNEL_CAPACITY).0,
+                state: self.state,
+            }),
         }
     }
 }
@@ -107,7 +167,42 @@ impl Server {
         Fut: std::future::Future<Output = Result<CallToolResult, McpError>> + Send + 'static,
     {
         let boxed_handler: ToolHandler = Box::new(move |req| Box::pin(handler(req)));
-        self.tool_handlers.write().await.insert(name.into(), boxed_handler);
+        self.inner.tool_handlers.write().await.insert(name.into(), boxed_handler);
+    }
+
+    /// Set a tool handler with typed argument extraction and access to
+    /// shared application state, removing the repetitive
+    /// `.and_then(|args| args.get(...))` dance of [`Server::set_tool_handler`].
+    ///
+    /// `request.arguments` is deserialized into `P` before `handler` runs;
+    /// a deserialization failure is turned into an `invalid_params` error
+    /// automatically. State of type `S` must have been registered with
+    /// [`ServerBuilder::with_state`], or the registered handler answers
+    /// every call with an internal error.
+    pub async fn set_tool_handler_typed<P, S, F, Fut>(&self, name: impl Into<String>, handler: F)
+    where
+        P: serde::de::DeserializeOwned + Send + 'static,
+        S: Send + Sync + 'static,
+        F: Fn(Params<P>, State<S>) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<CallToolResult, McpError>> + Send + 'static,
+    {
+        let state = self.inner.state.clone();
+        let boxed_handler: ToolHandler = Box::new(move |req: CallToolRequest| {
+            let state = state.clone();
+            Box::pin(async move {
+                let params: P = match req.arguments {
+                    Some(args) => serde_json::from_value(args)
+                        .map_err(|e| McpError::invalid_params(&format!("Invalid arguments: {e}")))?,
+                    None => serde_json::from_value(serde_json::Value::Object(Default::default()))
+                        .map_err(|e| McpError::invalid_params(&format!("Invalid arguments: {e}")))?,
+                };
+                let state = state
+                    .and_then(|s| s.downcast::<S>().ok())
+                    .ok_or_else(|| McpError::internal_error("No state registered via ServerBuilder::with_state"))?;
+                handler(Params(params), State(state)).await
+            })
+        });
+        self.inner.tool_handlers.write().await.insert(name.into(), boxed_handler);
     }
 
     /// Set a resource handler
@@ -117,7 +212,7 @@ impl Server {
         Fut: std::future::Future<Output = Result<ReadResourceResult, McpError>> + Send + 'static,
     {
         let boxed_handler: ResourceHandler = Box::new(move |req| Box::pin(handler(req)));
-        self.resource_handlers.write().await.insert("default".to_string(), boxed_handler);
+        self.inner.resource_handlers.write().await.insert("default".to_string(), boxed_handler);
     }
 
     /// Set a prompt handler
@@ -127,52 +222,264 @@ impl Server {
         Fut: std::future::Future<Output = Result<GetPromptResult, McpError>> + Send + 'static,
     {
         let boxed_handler: PromptHandler = Box::new(move |req| Box::pin(handler(req)));
-        self.prompt_handlers.write().await.insert(name.into(), boxed_handler);
+        self.inner.prompt_handlers.write().await.insert(name.into(), boxed_handler);
+    }
+
+    /// Set a handler for a notification method (a JSON-RPC call with no `id`),
+    /// such as `notifications/initialized` or `notifications/cancelled`.
+    ///
+    /// Notification handlers never produce a response; the transport is not
+    /// written to after one runs. `notifications/cancelled` is additionally
+    /// always honored internally (see [`Server::run`]) regardless of whether
+    /// a handler is registered for it.
+    pub async fn set_notification_handler<F, Fut>(&self, method: impl Into<String>, handler: F)
+    where
+        F: Fn(JsonRpcRequest) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let boxed_handler: NotificationHandler = Box::new(move |req| Box::pin(handler(req)));
+        self.inner.notification_handlers.write().await.insert(method.into(), boxed_handler);
+    }
+
+    /// Notify subscribers that the resource at `uri` has changed, pushing a
+    /// `notifications/resources/updated` notification to every connection
+    /// currently subscribed to it.
+    ///
+    /// This is fire-and-forget: if no run loop is currently subscribed to the
+    /// internal broadcast channel, the update is simply dropped.
+    pub fn notify_resource_updated(&self, uri: impl Into<String>) {
+        let _ = self.inner.resource_updates.send(uri.into());
     }
 
     /// Run the server with STDIO transport
     #[cfg(feature = "stdio")]
     pub async fn run_stdio(&self) -> Result<(), ServerError> {
         use crate::StdioTransport;
-        let mut transport = StdioTransport::new();
+        let transport = StdioTransport::new();
         self.run(transport).await
     }
 
-    /// Run the server with a custom transport
-    pub async fn run<T: Transport>(&self, mut transport: T) -> Result<(), ServerError> {
+    /// Accept TCP connections at `addr` and run one [`Server::run`] dispatch
+    /// loop per connection, each on its own task.
+    ///
+    /// Connections share this server's handler registries (they are
+    /// `Arc`-backed), so registering a handler takes effect for every
+    /// connection, existing and future.
+    #[cfg(feature = "tcp")]
+    pub async fn serve_tcp(&self, addr: impl tokio::net::ToSocketAddrs) -> Result<(), ServerError> {
+        use crate::TcpTransport;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
         loop {
-            let request = transport.receive_request().await?;
-            let response = self.handle_request(request).await;
-            transport.send_response(response).await?;
+            let (stream, _) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let _ = server.run(TcpTransport::new(stream)).await;
+            });
+        }
+    }
+
+    /// Run the server with a custom transport.
+    ///
+    /// Each decoded request is dispatched on its own `tokio::task` so a slow
+    /// handler never blocks other in-flight requests (including sibling
+    /// elements of the same batch), and responses may therefore complete out
+    /// of receive order, which JSON-RPC permits. In-flight requests are
+    /// tracked by id so that a `notifications/cancelled` notification can
+    /// abort the matching task.
+    ///
+    /// `transport` is owned by this loop rather than shared behind a lock:
+    /// spawned request tasks hand their finished response back over an
+    /// internal channel instead of writing to the transport directly, so a
+    /// pending read (every transport's `receive_message` blocks until more
+    /// bytes arrive) can never hold up writing a response that's ready to
+    /// go out. Inbound messages, outbound responses, and outbound
+    /// resource-update notifications are all serviced from the same loop
+    /// via `select!`.
+    pub async fn run<T: Transport + Send + 'static>(&self, mut transport: T) -> Result<(), ServerError> {
+        let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel::<serde_json::Value>();
+        let mut resource_updates = self.inner.resource_updates.subscribe();
+        let connection = Arc::new(ConnectionState::default());
+        loop {
+            tokio::select! {
+                message = transport.receive_message() => {
+                    let message = message?;
+                    let server = self.clone();
+                    let outbound_tx = outbound_tx.clone();
+                    let connection = Arc::clone(&connection);
+                    tokio::spawn(async move { server.dispatch_message(message, outbound_tx, connection).await });
+                }
+                Some(message) = outbound_rx.recv() => {
+                    transport.send_message(message).await?;
+                }
+                Ok(uri) = resource_updates.recv() => {
+                    if connection.subscribed_resources.read().await.contains(&uri) {
+                        let notification = JsonRpcNotification {
+                            method: "notifications/resources/updated".to_string(),
+                            params: Some(serde_json::json!({ "uri": uri })),
+                        };
+                        transport.send_notification(notification).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Dispatch a raw inbound JSON-RPC message, which may be a single
+    /// request or a JSON-RPC 2.0 batch (a JSON array of requests), handing
+    /// the correlated response(s) back to the run loop over `outbound` once
+    /// everything in the message has completed. `connection` is this run
+    /// loop's per-connection state (e.g. resource subscriptions).
+    async fn dispatch_message(
+        &self,
+        message: serde_json::Value,
+        outbound: mpsc::UnboundedSender<serde_json::Value>,
+        connection: Arc<ConnectionState>,
+    ) {
+        let response = match message {
+            serde_json::Value::Array(requests) if !requests.is_empty() => {
+                let responses = futures::future::join_all(
+                    requests.into_iter().map(|value| self.dispatch_value(value, Arc::clone(&connection))),
+                )
+                .await;
+                let responses: Vec<JsonRpcResponse> = responses.into_iter().flatten().collect();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::to_value(responses).unwrap())
+                }
+            }
+            serde_json::Value::Object(_) => self
+                .dispatch_value(message, connection)
+                .await
+                .map(|response| serde_json::to_value(response).unwrap()),
+            _ => Some(serde_json::to_value(JsonRpcResponse::error(
+                None,
+                McpError::invalid_request("Request must be a JSON-RPC object or a non-empty batch array"),
+            )).unwrap()),
+        };
+
+        if let Some(response) = response {
+            let _ = outbound.send(response);
+        }
+    }
+
+    /// Decode a single JSON-RPC request value and run it to completion,
+    /// returning `None` only when the element was a notification (no `id`).
+    /// A malformed element is answered with an `invalid_request` error whose
+    /// `id` is null, since no `id` could be read from it.
+    async fn dispatch_value(&self, value: serde_json::Value, connection: Arc<ConnectionState>) -> Option<JsonRpcResponse> {
+        match serde_json::from_value::<JsonRpcRequest>(value) {
+            Ok(request) if request.id.is_none() => {
+                self.handle_notification(request, connection).await;
+                None
+            }
+            Ok(request) => Some(self.run_request(request, connection).await),
+            Err(_) => Some(JsonRpcResponse::error(
+                None,
+                McpError::invalid_request("Malformed JSON-RPC request"),
+            )),
+        }
+    }
+
+    /// Run a request's handler on its own task, tracked in the connection's
+    /// `in_flight` map by id so a concurrent `notifications/cancelled` *on
+    /// the same connection* can abort it.
+    async fn run_request(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) -> JsonRpcResponse {
+        let id = request.id.clone();
+        let key = in_flight_key(&id);
+
+        let server = self.clone();
+        let task_connection = Arc::clone(&connection);
+        let join_handle = tokio::spawn(async move { server.handle_request(request, task_connection).await });
+        connection.in_flight.lock().await.insert(key.clone(), join_handle.abort_handle());
+
+        let result = join_handle.await;
+        connection.in_flight.lock().await.remove(&key);
+
+        match result {
+            Ok(response) => response,
+            Err(_) => JsonRpcResponse::error(id, McpError::internal_error("Request was cancelled")),
         }
     }
 
-    async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+    /// Run the registered notification handler for `request.method`, if any.
+    /// `notifications/cancelled` is always honored, independent of whether a
+    /// handler is registered for it. Unrecognized notifications are silently
+    /// ignored per the JSON-RPC spec.
+    async fn handle_notification(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) {
+        if request.method == "notifications/cancelled" {
+            self.cancel_in_flight_request(&request, &connection).await;
+        }
+
+        let handlers = self.inner.notification_handlers.read().await;
+        if let Some(handler) = handlers.get(&request.method) {
+            handler(request).await;
+        }
+    }
+
+    /// Abort the in-flight task named by a `notifications/cancelled`
+    /// notification's `requestId` param, if it is still running *on this
+    /// connection*. A `requestId` naming another connection's request (ids
+    /// are only unique per-connection, not server-wide) has no effect.
+    async fn cancel_in_flight_request(&self, request: &JsonRpcRequest, connection: &ConnectionState) {
+        let Some(cancelled_id) = request.params.as_ref().and_then(|p| p.get("requestId")) else {
+            return;
+        };
+        let key = in_flight_key(cancelled_id);
+        if let Some(handle) = connection.in_flight.lock().await.remove(&key) {
+            handle.abort();
+        }
+    }
+
+    async fn handle_request(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) -> JsonRpcResponse {
         match request.method.as_str() {
-            "initialize" => self.handle_initialize(request).await,
+            "initialize" => self.handle_initialize(request, connection).await,
             "tools/list" => self.handle_list_tools(request).await,
             "tools/call" => self.handle_call_tool(request).await,
             "resources/list" => self.handle_list_resources(request).await,
             "resources/read" => self.handle_read_resource(request).await,
+            "resources/subscribe" => self.handle_subscribe_resource(request, connection).await,
+            "resources/unsubscribe" => self.handle_unsubscribe_resource(request, connection).await,
             "prompts/list" => self.handle_list_prompts(request).await,
             "prompts/get" => self.handle_get_prompt(request).await,
             _ => JsonRpcResponse::error(request.id, McpError::method_not_found(&request.method)),
         }
     }
 
-    async fn handle_initialize(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+    async fn handle_initialize(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) -> JsonRpcResponse {
+        let client_request: InitializeRequest = match request.params.as_ref().and_then(|p| serde_json::from_value(p.clone()).ok()) {
+            Some(req) => req,
+            None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Invalid initialize request")),
+        };
+
+        let negotiated_version = match negotiate_protocol_version(&client_request.protocol_version) {
+            Some(version) => version,
+            None => {
+                return JsonRpcResponse::error(
+                    request.id,
+                    McpError::invalid_params(&format!(
+                        "Unsupported protocol version \"{}\"; this server supports: {}",
+                        client_request.protocol_version,
+                        SUPPORTED_PROTOCOL_VERSIONS.join(", "),
+                    )),
+                );
+            }
+        };
+
+        *connection.negotiated_version.write().await = Some(negotiated_version.clone());
+
         let result = InitializeResult {
-            protocol_version: MCP_VERSION.to_string(),
-            capabilities: self.capabilities.clone(),
-            server_info: self.info.clone(),
-            instructions: None,
+            protocol_version: negotiated_version,
+            capabilities: self.inner.capabilities.clone(),
+            server_info: self.inner.info.clone(),
+            instructions: self.inner.instructions.clone(),
         };
         JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
     }
 
     async fn handle_list_tools(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         let result = ListToolsResult {
-            tools: self.tools.clone(),
+            tools: self.inner.tools.clone(),
             next_cursor: None,
         };
         JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
@@ -184,7 +491,7 @@ impl Server {
             None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Invalid tool request")),
         };
 
-        let handlers = self.tool_handlers.read().await;
+        let handlers = self.inner.tool_handlers.read().await;
         match handlers.get(&tool_request.name) {
             Some(handler) => {
                 match handler(tool_request).await {
@@ -198,7 +505,7 @@ impl Server {
 
     async fn handle_list_resources(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         let result = ListResourcesResult {
-            resources: self.resources.clone(),
+            resources: self.inner.resources.clone(),
             next_cursor: None,
         };
         JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
@@ -210,7 +517,7 @@ impl Server {
             None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Invalid resource request")),
         };
 
-        let handlers = self.resource_handlers.read().await;
+        let handlers = self.inner.resource_handlers.read().await;
         match handlers.get("default") {
             Some(handler) => {
                 match handler(resource_request).await {
@@ -222,9 +529,29 @@ impl Server {
         }
     }
 
+    async fn handle_subscribe_resource(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) -> JsonRpcResponse {
+        let uri = match request.params.as_ref().and_then(|p| p.get("uri")).and_then(|v| v.as_str()) {
+            Some(uri) => uri.to_string(),
+            None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Missing \"uri\"")),
+        };
+
+        connection.subscribed_resources.write().await.insert(uri);
+        JsonRpcResponse::success(request.id, serde_json::json!({}))
+    }
+
+    async fn handle_unsubscribe_resource(&self, request: JsonRpcRequest, connection: Arc<ConnectionState>) -> JsonRpcResponse {
+        let uri = match request.params.as_ref().and_then(|p| p.get("uri")).and_then(|v| v.as_str()) {
+            Some(uri) => uri.to_string(),
+            None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Missing \"uri\"")),
+        };
+
+        connection.subscribed_resources.write().await.remove(&uri);
+        JsonRpcResponse::success(request.id, serde_json::json!({}))
+    }
+
     async fn handle_list_prompts(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         let result = ListPromptsResult {
-            prompts: self.prompts.clone(),
+            prompts: self.inner.prompts.clone(),
             next_cursor: None,
         };
         JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
@@ -236,7 +563,7 @@ impl Server {
             None => return JsonRpcResponse::error(request.id, McpError::invalid_params("Invalid prompt request")),
         };
 
-        let handlers = self.prompt_handlers.read().await;
+        let handlers = self.inner.prompt_handlers.read().await;
         match handlers.get(&prompt_request.name) {
             Some(handler) => {
                 match handler(prompt_request).await {
@@ -247,4 +574,318 @@ impl Server {
             None => JsonRpcResponse::error(request.id, McpError::method_not_found(&format!("Prompt not found: {}", prompt_request.name))),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Validate `requested` against [`SUPPORTED_PROTOCOL_VERSIONS`]. With a
+/// single supported version this is an exact-match check, not a
+/// highest-mutually-supported-version negotiation; returns `None` if the
+/// client's version isn't the one we support.
+fn negotiate_protocol_version(requested: &str) -> Option<String> {
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|&&version| version == requested)
+        .map(|&version| version.to_string())
+}
+
+/// Build the `in_flight` map key for a JSON-RPC id (or the raw `requestId`
+/// value out of a `notifications/cancelled` payload). Keying off the
+/// canonical JSON encoding avoids relying on `RequestId` implementing `Hash`.
+fn in_flight_key<T: serde::Serialize>(id: T) -> String {
+    serde_json::to_string(&id).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::Notify;
+
+    /// Transport driven by an inbound `mpsc` channel, so a test can control
+    /// exactly when each message becomes available to `Server::run`, and
+    /// records every outbound message for later inspection. Closing the
+    /// sender makes `receive_message` return an error, which ends `run`.
+    struct MockTransport {
+        inbound: mpsc::UnboundedReceiver<serde_json::Value>,
+        outbound: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    impl MockTransport {
+        fn new() -> (Self, mpsc::UnboundedSender<serde_json::Value>, Arc<Mutex<Vec<serde_json::Value>>>) {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let outbound = Arc::new(Mutex::new(Vec::new()));
+            (Self { inbound: rx, outbound: Arc::clone(&outbound) }, tx, outbound)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for MockTransport {
+        async fn receive_message(&mut self) -> Result<serde_json::Value, ServerError> {
+            self.inbound
+                .recv()
+                .await
+                .ok_or_else(|| ServerError::Transport("transport closed".to_string()))
+        }
+
+        async fn send_message(&mut self, message: serde_json::Value) -> Result<(), ServerError> {
+            self.outbound.lock().await.push(message);
+            Ok(())
+        }
+    }
+
+    fn request(id: i64, method: &str) -> serde_json::Value {
+        serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method })
+    }
+
+    fn notification(method: &str, params: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    /// Poll `outbound` until it has at least `count` messages; panics if that
+    /// takes longer than five seconds.
+    async fn wait_for_outbound(outbound: &Arc<Mutex<Vec<serde_json::Value>>>, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if outbound.lock().await.len() >= count {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for outbound message");
+    }
+
+    #[tokio::test]
+    async fn dispatches_a_batch_and_answers_with_a_single_batch_response() {
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+        let (transport, tx, outbound) = MockTransport::new();
+        let run = tokio::spawn(async move { server.run(transport).await });
+
+        let batch = serde_json::Value::Array(vec![request(1, "tools/list"), request(2, "prompts/list")]);
+        tx.send(batch).unwrap();
+        wait_for_outbound(&outbound, 1).await;
+        drop(tx);
+        let _ = run.await;
+
+        let sent = outbound.lock().await;
+        assert_eq!(sent.len(), 1);
+        let responses = sent[0].as_array().expect("batch response should be a JSON array");
+        let ids: Vec<_> = responses.iter().map(|r| r["id"].clone()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&serde_json::json!(1)));
+        assert!(ids.contains(&serde_json::json!(2)));
+    }
+
+    #[tokio::test]
+    async fn notification_runs_its_handler_and_produces_no_response() {
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+        let notified = Arc::new(Notify::new());
+        let notified_handler = Arc::clone(&notified);
+        server
+            .set_notification_handler("notifications/initialized", move |_req| {
+                let notified = Arc::clone(&notified_handler);
+                async move {
+                    notified.notify_one();
+                }
+            })
+            .await;
+
+        let (transport, tx, outbound) = MockTransport::new();
+        let run = tokio::spawn(async move { server.run(transport).await });
+
+        tx.send(notification("notifications/initialized", serde_json::json!({}))).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), notified.notified())
+            .await
+            .expect("notification handler never ran");
+        drop(tx);
+        let _ = run.await;
+
+        assert!(outbound.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn single_request_round_trip_completes_without_further_client_input() {
+        // Regression test for a deadlock where the run loop's pending read
+        // blocked a spawned task's write of an already-computed response.
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+        let (transport, tx, outbound) = MockTransport::new();
+        let run = tokio::spawn(async move { server.run(transport).await });
+
+        tx.send(request(1, "tools/list")).unwrap();
+        wait_for_outbound(&outbound, 1).await;
+        drop(tx);
+        let _ = run.await;
+
+        let sent = outbound.lock().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["id"], serde_json::json!(1));
+    }
+
+    #[tokio::test]
+    async fn cancelled_notification_aborts_the_matching_in_flight_request() {
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+        let started = Arc::new(Notify::new());
+        let started_handler = Arc::clone(&started);
+        server
+            .set_tool_handler("slow", move |_req| {
+                let started = Arc::clone(&started_handler);
+                async move {
+                    started.notify_one();
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(CallToolResult { content: vec![ToolResultContent::text("too late")], is_error: None })
+                }
+            })
+            .await;
+
+        let (transport, tx, outbound) = MockTransport::new();
+        let run = tokio::spawn(async move { server.run(transport).await });
+
+        let mut call = request(1, "tools/call");
+        call["params"] = serde_json::json!({ "name": "slow", "arguments": {} });
+        tx.send(call).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), started.notified())
+            .await
+            .expect("tool handler never started");
+
+        tx.send(notification("notifications/cancelled", serde_json::json!({ "requestId": 1 })))
+            .unwrap();
+        wait_for_outbound(&outbound, 1).await;
+        drop(tx);
+        let _ = run.await;
+
+        let sent = outbound.lock().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["id"], serde_json::json!(1));
+        assert!(sent[0].get("error").is_some(), "cancelled request should answer with an error, got {:?}", sent[0]);
+    }
+
+    #[test]
+    fn negotiate_protocol_version_accepts_the_supported_version() {
+        assert_eq!(negotiate_protocol_version(MCP_VERSION), Some(MCP_VERSION.to_string()));
+    }
+
+    #[test]
+    fn negotiate_protocol_version_rejects_an_unsupported_version() {
+        assert_eq!(negotiate_protocol_version("1999-01-01"), None);
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_are_isolated_per_connection() {
+        // Two connections picking the same JSON-RPC id is normal (each
+        // client numbers its own requests); a cancel on one connection must
+        // not reach across and abort the other's in-flight request.
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+        let started = Arc::new(Notify::new());
+        let started_handler = Arc::clone(&started);
+        server
+            .set_tool_handler("slow", move |_req| {
+                let started = Arc::clone(&started_handler);
+                async move {
+                    started.notify_one();
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(CallToolResult { content: vec![ToolResultContent::text("too late")], is_error: None })
+                }
+            })
+            .await;
+
+        let (transport_a, tx_a, outbound_a) = MockTransport::new();
+        let run_a = tokio::spawn({
+            let server = server.clone();
+            async move { server.run(transport_a).await }
+        });
+        let (transport_b, tx_b, outbound_b) = MockTransport::new();
+        let run_b = tokio::spawn({
+            let server = server.clone();
+            async move { server.run(transport_b).await }
+        });
+
+        let mut call = request(1, "tools/call");
+        call["params"] = serde_json::json!({ "name": "slow", "arguments": {} });
+        tx_a.send(call).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), started.notified())
+            .await
+            .expect("tool handler never started");
+
+        // Connection B cancels its own (never sent) request id 1; this must
+        // have no effect on connection A's in-flight request of the same id.
+        tx_b.send(notification("notifications/cancelled", serde_json::json!({ "requestId": 1 })))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(outbound_a.lock().await.is_empty(), "connection B's cancel must not abort connection A's request");
+
+        // Now cancel it on connection A itself, proving same-connection
+        // cancellation still works after the cross-connection no-op above.
+        tx_a.send(notification("notifications/cancelled", serde_json::json!({ "requestId": 1 })))
+            .unwrap();
+        wait_for_outbound(&outbound_a, 1).await;
+        drop(tx_a);
+        drop(tx_b);
+        let _ = run_a.await;
+        let _ = run_b.await;
+
+        let sent_a = outbound_a.lock().await;
+        assert_eq!(sent_a.len(), 1);
+        assert!(sent_a[0].get("error").is_some());
+        assert!(outbound_b.lock().await.is_empty());
+    }
+
+    fn initialize_request(id: i64) -> serde_json::Value {
+        let mut req = request(id, "initialize");
+        req["params"] = serde_json::json!({
+            "protocolVersion": MCP_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1.0" },
+        });
+        req
+    }
+
+    #[tokio::test]
+    async fn resource_subscriptions_are_isolated_per_connection() {
+        let server = ServerBuilder::new("test-server", "0.1.0").build();
+
+        let (transport_a, tx_a, outbound_a) = MockTransport::new();
+        let run_a = tokio::spawn({
+            let server = server.clone();
+            async move { server.run(transport_a).await }
+        });
+        let (transport_b, tx_b, outbound_b) = MockTransport::new();
+        let run_b = tokio::spawn({
+            let server = server.clone();
+            async move { server.run(transport_b).await }
+        });
+
+        // Connection A negotiates a version and subscribes to a resource;
+        // connection B does neither, concurrently.
+        tx_a.send(initialize_request(1)).unwrap();
+        wait_for_outbound(&outbound_a, 1).await;
+
+        let mut subscribe = request(2, "resources/subscribe");
+        subscribe["params"] = serde_json::json!({ "uri": "file:///a" });
+        tx_a.send(subscribe).unwrap();
+        wait_for_outbound(&outbound_a, 2).await;
+
+        // Connection B independently negotiates its own version; its
+        // response must be unaffected by A already having done so.
+        tx_b.send(initialize_request(1)).unwrap();
+        wait_for_outbound(&outbound_b, 1).await;
+
+        // B, which never subscribed, must not observe A's resource
+        // subscription: a server-initiated update for the same URI should
+        // reach only A.
+        server.notify_resource_updated("file:///a");
+        wait_for_outbound(&outbound_a, 3).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        drop(tx_a);
+        drop(tx_b);
+        let _ = run_a.await;
+        let _ = run_b.await;
+
+        let sent_a = outbound_a.lock().await;
+        assert_eq!(sent_a.len(), 3);
+        assert_eq!(sent_a[2]["method"], serde_json::json!("notifications/resources/updated"));
+        let sent_b = outbound_b.lock().await;
+        assert_eq!(sent_b.len(), 1);
+        assert!(sent_b[0].get("error").is_none(), "B's own initialize should succeed regardless of A's state");
+    }
+}
@@ -5,7 +5,7 @@ use crate::{Transport, ServerError};
 #[cfg(feature = "stdio")]
 use async_trait::async_trait;
 #[cfg(feature = "stdio")]
-use mcp_protocol_types::{JsonRpcRequest, JsonRpcResponse};
+use serde_json::Value;
 #[cfg(feature = "stdio")]
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, stdin, stdout};
 
@@ -30,18 +30,18 @@ impl StdioTransport {
 #[cfg(feature = "stdio")]
 #[async_trait]
 impl Transport for StdioTransport {
-    async fn receive_request(&mut self) -> Result<JsonRpcRequest, ServerError> {
+    async fn receive_message(&mut self) -> Result<Value, ServerError> {
         let mut line = String::new();
         self.reader.read_line(&mut line).await?;
-        let request: JsonRpcRequest = serde_json::from_str(&line)?;
-        Ok(request)
+        let message: Value = serde_json::from_str(&line)?;
+        Ok(message)
     }
 
-    async fn send_response(&mut self, response: JsonRpcResponse) -> Result<(), ServerError> {
-        let json = serde_json::to_string(&response)?;
+    async fn send_message(&mut self, message: Value) -> Result<(), ServerError> {
+        let json = serde_json::to_string(&message)?;
         self.writer.write_all(json.as_bytes()).await?;
         self.writer.write_all(b"\n").await?;
         self.writer.flush().await?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,56 @@
+//! TCP transport implementation.
+
+#[cfg(feature = "tcp")]
+use crate::{Transport, ServerError};
+#[cfg(feature = "tcp")]
+use async_trait::async_trait;
+#[cfg(feature = "tcp")]
+use serde_json::Value;
+#[cfg(feature = "tcp")]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(feature = "tcp")]
+use tokio::net::TcpStream;
+#[cfg(feature = "tcp")]
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+#[cfg(feature = "tcp")]
+/// TCP transport for MCP communication, framed the same
+/// newline-delimited way as [`crate::StdioTransport`].
+pub struct TcpTransport {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+#[cfg(feature = "tcp")]
+impl TcpTransport {
+    /// Wrap an accepted TCP stream as a transport.
+    pub fn new(stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+        }
+    }
+}
+
+#[cfg(feature = "tcp")]
+#[async_trait]
+impl Transport for TcpTransport {
+    async fn receive_message(&mut self) -> Result<Value, ServerError> {
+        let mut line = String::new();
+        let bytes_read = self.reader.read_line(&mut line).await?;
+        if bytes_read == 0 {
+            return Err(ServerError::Transport("Connection closed".to_string()));
+        }
+        let message: Value = serde_json::from_str(&line)?;
+        Ok(message)
+    }
+
+    async fn send_message(&mut self, message: Value) -> Result<(), ServerError> {
+        let json = serde_json::to_string(&message)?;
+        self.writer.write_all(json.as_bytes()).await?;
+        self.writer.write_all(b"\n").await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
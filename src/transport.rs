@@ -1,15 +1,43 @@
 //! Transport trait and implementations.
 
 use async_trait::async_trait;
-use mcp_protocol_types::{JsonRpcRequest, JsonRpcResponse};
+use mcp_protocol_types::JsonRpcNotification;
+use serde_json::Value;
 use crate::error::ServerError;
 
-/// Transport trait for MCP communication
+/// Transport trait for MCP communication.
+///
+/// A transport deals in raw JSON-RPC frames rather than a single concrete
+/// request/response type, since JSON-RPC 2.0 allows a client to send either
+/// one request object or a batch (a JSON array of request objects), and the
+/// server must answer with the same shape.
 #[async_trait]
 pub trait Transport {
-    /// Receive a request from the transport
-    async fn receive_request(&mut self) -> Result<JsonRpcRequest, ServerError>;
-    
-    /// Send a response through the transport
-    async fn send_response(&mut self, response: JsonRpcResponse) -> Result<(), ServerError>;
-}
\ No newline at end of file
+    /// Receive a raw JSON-RPC message from the transport.
+    ///
+    /// The returned value is either a single JSON-RPC request/notification
+    /// object or a JSON array of such objects (a batch).
+    async fn receive_message(&mut self) -> Result<Value, ServerError>;
+
+    /// Send a raw JSON-RPC message through the transport.
+    ///
+    /// `message` is either a single JSON object (a response, or a
+    /// server-initiated notification passed on by
+    /// [`Transport::send_notification`]) or a JSON array of response objects
+    /// answering a batch.
+    async fn send_message(&mut self, message: Value) -> Result<(), ServerError>;
+
+    /// Send a server-initiated notification through the transport, e.g.
+    /// `notifications/resources/updated`. Notifications carry no `id` and
+    /// expect no reply.
+    ///
+    /// The default implementation just serializes the notification and
+    /// funnels it through [`Transport::send_message`], which is correct for
+    /// any transport that frames a single JSON value per message.
+    async fn send_notification(
+        &mut self,
+        notification: JsonRpcNotification,
+    ) -> Result<(), ServerError> {
+        self.send_message(serde_json::to_value(notification)?).await
+    }
+}